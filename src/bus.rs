@@ -0,0 +1,155 @@
+use std::ops::RangeInclusive;
+
+// Lets the CPU's memory accesses target any backing store, not just flat RAM.
+pub trait Bus {
+    fn read(&self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, data: u8);
+
+    fn read_u16(&self, addr: u16) -> u16 {
+        let lo = self.read(addr) as u16;
+        let hi = self.read(addr.wrapping_add(1)) as u16;
+        (hi << 8) | lo
+    }
+
+    fn write_u16(&mut self, addr: u16, data: u16) {
+        let hi = (data >> 8) as u8;
+        let lo = (data & 0xff) as u8;
+        self.write(addr, lo);
+        self.write(addr.wrapping_add(1), hi);
+    }
+}
+
+/// Flat 64KB RAM covering the full `0x0000..=0xFFFF` address space.
+pub struct Ram {
+    memory: [u8; 0x10000],
+}
+
+impl Ram {
+    pub fn new() -> Self {
+        Ram {
+            memory: [0; 0x10000],
+        }
+    }
+}
+
+impl Default for Ram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus for Ram {
+    fn read(&self, addr: u16) -> u8 {
+        self.memory[addr as usize]
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        self.memory[addr as usize] = data;
+    }
+}
+
+/// A peripheral that intercepts reads/writes within a registered address range.
+pub trait MmioHandler {
+    fn read(&self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+}
+
+/// A bus that probes registered handlers before falling through to RAM.
+pub struct MappedBus {
+    ram: Ram,
+    handlers: Vec<(RangeInclusive<u16>, Box<dyn MmioHandler>)>,
+}
+
+impl MappedBus {
+    pub fn new() -> Self {
+        MappedBus {
+            ram: Ram::new(),
+            handlers: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, range: RangeInclusive<u16>, handler: Box<dyn MmioHandler>) {
+        self.handlers.push((range, handler));
+    }
+}
+
+impl Default for MappedBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus for MappedBus {
+    fn read(&self, addr: u16) -> u8 {
+        for (range, handler) in &self.handlers {
+            if range.contains(&addr) {
+                return handler.read(addr);
+            }
+        }
+        self.ram.read(addr)
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        for (range, handler) in &mut self.handlers {
+            if range.contains(&addr) {
+                handler.write(addr, data);
+                return;
+            }
+        }
+        self.ram.write(addr, data);
+    }
+}
+
+/// Minimal latch: the last byte written is read back until overwritten.
+#[derive(Default)]
+pub struct KeyboardLatch {
+    value: u8,
+}
+
+impl KeyboardLatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MmioHandler for KeyboardLatch {
+    fn read(&self, _addr: u16) -> u8 {
+        self.value
+    }
+
+    fn write(&mut self, _addr: u16, value: u8) {
+        self.value = value;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ram_read_write_round_trip() {
+        let mut ram = Ram::new();
+        ram.write(0x1234, 0x56);
+        assert_eq!(ram.read(0x1234), 0x56);
+        assert_eq!(ram.read(0x0000), 0x00);
+    }
+
+    #[test]
+    fn unmapped_addresses_fall_through_to_ram() {
+        let mut bus = MappedBus::new();
+        bus.register(0x4000..=0x4000, Box::new(KeyboardLatch::new()));
+
+        bus.write(0x0010, 0x42);
+        assert_eq!(bus.read(0x0010), 0x42);
+    }
+
+    #[test]
+    fn mapped_addresses_go_to_the_handler() {
+        let mut bus = MappedBus::new();
+        bus.register(0x4000..=0x4000, Box::new(KeyboardLatch::new()));
+
+        bus.write(0x4000, 0x41);
+        assert_eq!(bus.read(0x4000), 0x41);
+        assert_eq!(bus.read(0x0000), 0x00);
+    }
+}
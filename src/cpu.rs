@@ -1,7 +1,26 @@
 use std::collections::HashMap;
 
+use crate::bus::{Bus, Ram};
+use crate::disasm;
 use crate::op_codes;
 
+/// Snapshot of CPU state at the start of one instruction, handed to a
+/// trace callback for logging/golden-log comparison.
+pub struct TraceState {
+    pub pc: u16,
+    pub register_a: u8,
+    pub register_x: u8,
+    pub register_y: u8,
+    pub status: u8,
+    pub register_s: u8,
+    pub mnemonic: String,
+    /// The instruction's raw opcode/operand bytes, e.g. `[0x4C, 0xF5, 0xC5]`,
+    /// for callers reconstructing a nestest-style byte-dump column.
+    pub bytes: Vec<u8>,
+}
+
+type TraceCallback = Box<dyn FnMut(&TraceState)>;
+
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
 pub enum AddressingMode {
@@ -14,30 +33,69 @@ pub enum AddressingMode {
     Absolute_Y,
     Indirect_X,
     Indirect_Y,
+    Relative,
     NoneAddressing,
 }
 
+const FLAG_CARRY: u8 = 0b0000_0001;
+const FLAG_ZERO: u8 = 0b0000_0010;
+const FLAG_INTERRUPT_DISABLE: u8 = 0b0000_0100;
+const FLAG_DECIMAL: u8 = 0b0000_1000;
+const FLAG_BREAK: u8 = 0b0001_0000;
+const FLAG_BREAK2: u8 = 0b0010_0000;
+const FLAG_OVERFLOW: u8 = 0b0100_0000;
+const FLAG_NEGATIVE: u8 = 0b1000_0000;
+
+const STACK: u16 = 0x0100;
+const STACK_RESET: u8 = 0xFD;
+
 pub struct CPU {
     pub register_a: u8,
     pub register_x: u8,
     pub register_y: u8,
     pub status: u8,
     pub pc: u16,
-    memory: [u8; 0xFFFF],
+    pub register_s: u8,
+    /// Running count of elapsed CPU cycles, so embedders (PPU/APU) can stay
+    /// in sync with the core.
+    pub cycles: u64,
+    /// Set by the current instruction when its `get_operand_address` call
+    /// reports that the effective address crossed a page boundary; `step`
+    /// turns it into the +1 cycle penalty after dispatch.
+    page_crossed: bool,
+    trace: Option<TraceCallback>,
+    bus: Box<dyn Bus>,
 }
 
 impl CPU {
     pub fn new() -> Self {
+        CPU::with_bus(Box::new(Ram::new()))
+    }
+
+    /// Builds a CPU around a caller-supplied bus, e.g. a cartridge/PPU/APU
+    /// address map, or a test fixture that only implements a few ranges.
+    pub fn with_bus(bus: Box<dyn Bus>) -> Self {
         CPU {
             register_a: 0,
             register_x: 0,
             register_y: 0,
             status: 0,
             pc: 0,
-            memory: [0; 0xFFFF],
+            register_s: STACK_RESET,
+            cycles: 0,
+            page_crossed: false,
+            trace: None,
+            bus,
         }
     }
 
+    /// Installs a callback invoked once per fetched instruction, before it
+    /// executes. Pass e.g. a closure that formats and logs nestest-style
+    /// trace lines. Costs nothing when left unset.
+    pub fn set_trace(&mut self, trace: TraceCallback) {
+        self.trace = Some(trace);
+    }
+
     // CPU specific
 
     // Resets the CPU state and sets the PC value to Reset Vector
@@ -45,12 +103,25 @@ impl CPU {
         self.register_a = 0;
         self.register_x = 0;
         self.status = 0;
+        self.register_s = STACK_RESET;
 
         self.pc = self.mem_read_u16(0xFFFC);
     }
 
+    /// Triggers a non-maskable interrupt: pushes PC and status, then vectors
+    /// through `0xFFFA`. Meant to be called between `run` steps by a device
+    /// (e.g. the PPU) once it exists on the bus.
+    pub fn nmi(&mut self) {
+        self.stack_push_u16(self.pc);
+        let flags = (self.status | FLAG_BREAK2) & !FLAG_BREAK;
+        self.stack_push(flags);
+        self.status |= FLAG_INTERRUPT_DISABLE;
+        self.pc = self.mem_read_u16(0xFFFA);
+    }
+
     fn lda(&mut self, mode: &AddressingMode) {
-        let addr = self.get_operand_address(mode);
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
         let value = self.mem_read(addr);
 
         self.register_a = value;
@@ -59,7 +130,7 @@ impl CPU {
     }
 
     fn sta(&mut self, mode: &AddressingMode) {
-        let addr = self.get_operand_address(mode);
+        let (addr, _) = self.get_operand_address(mode);
         self.mem_write(addr, self.register_a);
     }
 
@@ -81,12 +152,365 @@ impl CPU {
         self.update_negative_flag(self.register_y);
     }
 
+    fn dex(&mut self) {
+        self.register_x = self.register_x.wrapping_sub(1);
+        self.update_zero_flag(self.register_x);
+        self.update_negative_flag(self.register_x);
+    }
+
+    fn dey(&mut self) {
+        self.register_y = self.register_y.wrapping_sub(1);
+        self.update_zero_flag(self.register_y);
+        self.update_negative_flag(self.register_y);
+    }
+
     fn tay(&mut self) {
         self.register_y = self.register_a;
         self.update_zero_flag(self.register_y);
         self.update_negative_flag(self.register_y);
     }
 
+    fn jsr(&mut self) {
+        let (target, _) = self.get_operand_address(&AddressingMode::Absolute);
+        self.stack_push_u16(self.pc.wrapping_add(1));
+        self.pc = target;
+    }
+
+    fn rts(&mut self) {
+        let addr = self.stack_pop_u16();
+        self.pc = addr.wrapping_add(1);
+    }
+
+    fn pha(&mut self) {
+        self.stack_push(self.register_a);
+    }
+
+    fn pla(&mut self) {
+        self.register_a = self.stack_pop();
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn php(&mut self) {
+        // The pushed copy always has B and the unused bit set; only the
+        // register that stays in the CPU reflects the "real" flags.
+        self.stack_push(self.status | FLAG_BREAK | FLAG_BREAK2);
+    }
+
+    fn plp(&mut self) {
+        self.status = (self.stack_pop() & !FLAG_BREAK) | FLAG_BREAK2;
+    }
+
+    fn brk(&mut self) {
+        self.stack_push_u16(self.pc.wrapping_add(1));
+        self.stack_push(self.status | FLAG_BREAK | FLAG_BREAK2);
+        self.status |= FLAG_INTERRUPT_DISABLE;
+        self.pc = self.mem_read_u16(0xFFFE);
+    }
+
+    fn rti(&mut self) {
+        self.status = (self.stack_pop() & !FLAG_BREAK) | FLAG_BREAK2;
+        self.pc = self.stack_pop_u16();
+    }
+
+    fn stack_push(&mut self, data: u8) {
+        self.mem_write(STACK + self.register_s as u16, data);
+        self.register_s = self.register_s.wrapping_sub(1);
+    }
+
+    fn stack_pop(&mut self) -> u8 {
+        self.register_s = self.register_s.wrapping_add(1);
+        self.mem_read(STACK + self.register_s as u16)
+    }
+
+    fn stack_push_u16(&mut self, data: u16) {
+        let hi = (data >> 8) as u8;
+        let lo = (data & 0xff) as u8;
+        self.stack_push(hi);
+        self.stack_push(lo);
+    }
+
+    fn stack_pop_u16(&mut self) -> u16 {
+        let lo = self.stack_pop() as u16;
+        let hi = self.stack_pop() as u16;
+        (hi << 8) | lo
+    }
+
+    fn adc(&mut self, mode: &AddressingMode) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        let value = self.mem_read(addr);
+        self.add_to_register_a(value);
+    }
+
+    fn sbc(&mut self, mode: &AddressingMode) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        let value = self.mem_read(addr);
+        // A - M - (1 - C) is the same addition used by ADC with the operand
+        // bit-flipped, which keeps the carry/overflow math in one place.
+        self.add_to_register_a(!value);
+    }
+
+    fn add_to_register_a(&mut self, value: u8) {
+        let sum = self.register_a as u16 + value as u16 + self.get_carry_flag() as u16;
+        let result = sum as u8;
+
+        self.set_carry_flag(sum > 0xFF);
+        self.set_overflow_flag((self.register_a ^ result) & (value ^ result) & 0x80 != 0);
+
+        self.register_a = result;
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn and(&mut self, mode: &AddressingMode) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        self.register_a &= self.mem_read(addr);
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn eor(&mut self, mode: &AddressingMode) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        self.register_a ^= self.mem_read(addr);
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn ora(&mut self, mode: &AddressingMode) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        self.register_a |= self.mem_read(addr);
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn asl_accumulator(&mut self) {
+        self.set_carry_flag(self.register_a & 0x80 != 0);
+        self.register_a <<= 1;
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn asl(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        let value = self.mem_read(addr);
+        self.set_carry_flag(value & 0x80 != 0);
+        let result = value << 1;
+        self.mem_write(addr, result);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn lsr_accumulator(&mut self) {
+        self.set_carry_flag(self.register_a & 0x01 != 0);
+        self.register_a >>= 1;
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn lsr(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        let value = self.mem_read(addr);
+        self.set_carry_flag(value & 0x01 != 0);
+        let result = value >> 1;
+        self.mem_write(addr, result);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn rol_accumulator(&mut self) {
+        let carry_in = self.get_carry_flag();
+        self.set_carry_flag(self.register_a & 0x80 != 0);
+        self.register_a = (self.register_a << 1) | carry_in;
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn rol(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        let value = self.mem_read(addr);
+        let carry_in = self.get_carry_flag();
+        self.set_carry_flag(value & 0x80 != 0);
+        let result = (value << 1) | carry_in;
+        self.mem_write(addr, result);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn ror_accumulator(&mut self) {
+        let carry_in = self.get_carry_flag();
+        self.set_carry_flag(self.register_a & 0x01 != 0);
+        self.register_a = (self.register_a >> 1) | (carry_in << 7);
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn ror(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        let value = self.mem_read(addr);
+        let carry_in = self.get_carry_flag();
+        self.set_carry_flag(value & 0x01 != 0);
+        let result = (value >> 1) | (carry_in << 7);
+        self.mem_write(addr, result);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn compare(&mut self, mode: &AddressingMode, register: u8) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        let value = self.mem_read(addr);
+        self.set_carry_flag(register >= value);
+        let result = register.wrapping_sub(value);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn cmp(&mut self, mode: &AddressingMode) {
+        let a = self.register_a;
+        self.compare(mode, a);
+    }
+
+    fn cpx(&mut self, mode: &AddressingMode) {
+        let x = self.register_x;
+        self.compare(mode, x);
+    }
+
+    fn cpy(&mut self, mode: &AddressingMode) {
+        let y = self.register_y;
+        self.compare(mode, y);
+    }
+
+    fn inc(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        let result = self.mem_read(addr).wrapping_add(1);
+        self.mem_write(addr, result);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn dec(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        let result = self.mem_read(addr).wrapping_sub(1);
+        self.mem_write(addr, result);
+        self.update_zero_flag(result);
+        self.update_negative_flag(result);
+    }
+
+    fn ldx(&mut self, mode: &AddressingMode) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        self.register_x = self.mem_read(addr);
+        self.update_zero_flag(self.register_x);
+        self.update_negative_flag(self.register_x);
+    }
+
+    fn ldy(&mut self, mode: &AddressingMode) {
+        let (addr, page_crossed) = self.get_operand_address(mode);
+        self.page_crossed = page_crossed;
+        self.register_y = self.mem_read(addr);
+        self.update_zero_flag(self.register_y);
+        self.update_negative_flag(self.register_y);
+    }
+
+    fn stx(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        self.mem_write(addr, self.register_x);
+    }
+
+    fn sty(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        self.mem_write(addr, self.register_y);
+    }
+
+    fn tsx(&mut self) {
+        self.register_x = self.register_s;
+        self.update_zero_flag(self.register_x);
+        self.update_negative_flag(self.register_x);
+    }
+
+    fn txs(&mut self) {
+        self.register_s = self.register_x;
+    }
+
+    fn txa(&mut self) {
+        self.register_a = self.register_x;
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn tya(&mut self) {
+        self.register_a = self.register_y;
+        self.update_zero_flag(self.register_a);
+        self.update_negative_flag(self.register_a);
+    }
+
+    fn bit(&mut self, mode: &AddressingMode) {
+        let (addr, _) = self.get_operand_address(mode);
+        let value = self.mem_read(addr);
+        self.update_zero_flag(self.register_a & value);
+        self.set_overflow_flag(value & FLAG_OVERFLOW != 0);
+        self.update_negative_flag(value);
+    }
+
+    fn jmp_absolute(&mut self) {
+        self.pc = self.mem_read_u16(self.pc);
+    }
+
+    fn jmp_indirect(&mut self) {
+        let ptr = self.mem_read_u16(self.pc);
+        // Faithful to the original 6502's page-boundary bug: if the pointer's
+        // low byte is 0xFF, the high byte wraps to the start of the same page
+        // instead of crossing into the next one.
+        self.pc = if ptr & 0x00FF == 0x00FF {
+            let lo = self.mem_read(ptr) as u16;
+            let hi = self.mem_read(ptr & 0xFF00) as u16;
+            (hi << 8) | lo
+        } else {
+            self.mem_read_u16(ptr)
+        };
+    }
+
+    fn branch(&mut self, condition: bool) {
+        if condition {
+            let next_instruction = self.pc.wrapping_add(1);
+            let (target, _) = self.get_operand_address(&AddressingMode::Relative);
+
+            self.cycles += 1;
+            if Self::page_crossed(next_instruction, target) {
+                self.cycles += 1;
+            }
+
+            self.pc = target;
+        } else {
+            self.pc = self.pc.wrapping_add(1);
+        }
+    }
+
+    fn set_carry_flag(&mut self, set: bool) {
+        if set {
+            self.status |= FLAG_CARRY;
+        } else {
+            self.status &= !FLAG_CARRY;
+        }
+    }
+
+    fn get_carry_flag(&self) -> u8 {
+        self.status & FLAG_CARRY
+    }
+
+    fn set_overflow_flag(&mut self, set: bool) {
+        if set {
+            self.status |= FLAG_OVERFLOW;
+        } else {
+            self.status &= !FLAG_OVERFLOW;
+        }
+    }
+
     fn update_zero_flag(&mut self, result: u8) {
         if result == 0 {
             self.status = self.status | 0b0000_0010;
@@ -103,42 +527,49 @@ impl CPU {
         }
     }
 
-    fn get_operand_address(&self, mode: &AddressingMode) -> u16 {
+    /// Resolves `mode`'s effective address, plus whether the indexed
+    /// addressing modes that can straddle a page (`Absolute_X/Y`,
+    /// `Indirect_Y`) actually crossed one.
+    fn get_operand_address(&self, mode: &AddressingMode) -> (u16, bool) {
         match mode {
-            AddressingMode::Immediate => self.pc,
-            AddressingMode::ZeroPage => self.mem_read(self.pc) as u16,
-            AddressingMode::Absolute => self.mem_read_u16(self.pc),
+            AddressingMode::Immediate => (self.pc, false),
+            AddressingMode::ZeroPage => (self.mem_read(self.pc) as u16, false),
+            AddressingMode::Absolute => (self.mem_read_u16(self.pc), false),
             AddressingMode::ZeroPage_X => {
                 let pos = self.mem_read(self.pc);
                 let addr = pos.wrapping_add(self.register_x) as u16;
-                addr
+                (addr, false)
             }
             AddressingMode::ZeroPage_Y => {
                 let pos = self.mem_read(self.pc);
                 let addr = pos.wrapping_add(self.register_y) as u16;
-                addr
+                (addr, false)
             }
             AddressingMode::Absolute_X => {
                 let base = self.mem_read_u16(self.pc);
                 let addr = base.wrapping_add(self.register_x as u16);
-                addr
+                (addr, Self::page_crossed(base, addr))
             }
             AddressingMode::Absolute_Y => {
                 let base = self.mem_read_u16(self.pc);
                 let addr = base.wrapping_add(self.register_y as u16);
-                addr
+                (addr, Self::page_crossed(base, addr))
             }
             AddressingMode::Indirect_X => {
                 let base = self.mem_read(self.pc);
                 let ptr = base.wrapping_add(self.register_x);
                 let addr = self.mem_read_u16(ptr as u16);
-                addr
+                (addr, false)
             }
             AddressingMode::Indirect_Y => {
                 let base = self.mem_read(self.pc);
                 let deref_base = self.mem_read_u16(base as u16);
                 let deref = deref_base.wrapping_add(self.register_y as u16);
-                deref
+                (deref, Self::page_crossed(deref_base, deref))
+            }
+            AddressingMode::Relative => {
+                let offset = self.mem_read(self.pc) as i8 as i16 as u16;
+                (self.pc.wrapping_add(1).wrapping_add(offset), false)
             }
             AddressingMode::NoneAddressing => {
                 panic!("mode {:?} is not supported", mode);
@@ -146,27 +577,31 @@ impl CPU {
         }
     }
 
+    fn page_crossed(base: u16, addr: u16) -> bool {
+        (base & 0xFF00) != (addr & 0xFF00)
+    }
+
+    /// Reads a byte without side effects, for debuggers/disassemblers.
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.mem_read(addr)
+    }
+
     // Memory specific
 
     fn mem_read(&self, addr: u16) -> u8 {
-        self.memory[addr as usize]
+        self.bus.read(addr)
     }
 
     fn mem_write(&mut self, addr: u16, data: u8) {
-        self.memory[addr as usize] = data;
+        self.bus.write(addr, data)
     }
 
     fn mem_read_u16(&self, pos: u16) -> u16 {
-        let lo = self.mem_read(pos) as u16;
-        let hi = self.mem_read(pos + 1) as u16;
-        (hi << 8) | (lo as u16)
+        self.bus.read_u16(pos)
     }
 
     fn mem_write_u16(&mut self, pos: u16, data: u16) {
-        let hi = (data >> 8) as u8;
-        let lo = (data & 0xff) as u8;
-        self.mem_write(pos, lo);
-        self.mem_write(pos + 1, hi);
+        self.bus.write_u16(pos, data)
     }
 
     // Execution
@@ -178,40 +613,189 @@ impl CPU {
     }
 
     fn load(&mut self, program: Vec<u8>) {
-        self.memory[0x8000..(0x8000 + program.len())].copy_from_slice(&program[..]);
+        for (i, byte) in program.iter().enumerate() {
+            self.mem_write(0x8000 + i as u16, *byte);
+        }
         self.mem_write_u16(0xFFFC, 0x8000);
     }
 
+    /// Loads `program` at an arbitrary origin without touching the reset
+    /// vector, for test harnesses that start execution somewhere other than
+    /// `0x8000`.
+    pub fn load_at(&mut self, origin: u16, program: &[u8]) {
+        for (i, byte) in program.iter().enumerate() {
+            self.mem_write(origin.wrapping_add(i as u16), *byte);
+        }
+    }
+
+    pub fn set_pc(&mut self, addr: u16) {
+        self.pc = addr;
+    }
+
+    /// Steps the CPU until the program counter stops advancing between
+    /// instructions, i.e. it traps on itself — the standard way functional
+    /// 6502 test ROMs (such as `6502_functional_test`) signal completion.
+    /// Returns the PC the CPU trapped at, or wherever it got to if
+    /// `max_cycles` elapsed first.
+    pub fn run_until_trap(&mut self, max_cycles: u64) -> u16 {
+        let start_cycles = self.cycles;
+
+        loop {
+            let pc_before = self.pc;
+            self.step();
+
+            if self.pc == pc_before || self.cycles.wrapping_sub(start_cycles) >= max_cycles {
+                return self.pc;
+            }
+        }
+    }
+
     fn run(&mut self) {
+        loop {
+            if self.step() {
+                return;
+            }
+        }
+    }
+
+    /// Fetches, decodes, and executes exactly one instruction. Returns
+    /// `true` if it was a `BRK`, which historically halts `run`.
+    fn step(&mut self) -> bool {
         let ref opcodes: HashMap<u8, &'static op_codes::OpCode> = *op_codes::OP_CODES_MAP;
 
-        loop {
-            let code = self.mem_read(self.pc);
-            self.pc += 1;
-
-            let old_pc = self.pc;
-
-            let opcode = opcodes.get(&code).expect("opcode ${code:x} not valid");
-
-            match code {
-                0xA9 | 0xA5 | 0xB5 | 0xAD | 0xBD | 0xB9 | 0xA1 | 0xB1 => {
-                    self.lda(&opcode.mode);
-                }
-                0x85 | 0x95 | 0x8D | 0x9D | 0x99 | 0x81 | 0x91 => {
-                    self.sta(&opcode.mode);
-                }
-                0xAA => self.tax(),
-                0xA8 => self.tay(),
-                0xE8 => self.inx(),
-                0xC8 => self.iny(),
-                0x00 => return,
-                _ => todo!(),
+        let code = self.mem_read(self.pc);
+        self.pc += 1;
+
+        let old_pc = self.pc;
+        self.page_crossed = false;
+
+        let opcode = opcodes.get(&code).expect("opcode ${code:x} not valid");
+
+        if let Some(mut trace) = self.trace.take() {
+            let instr_addr = old_pc.wrapping_sub(1);
+            let (mnemonic, _) = disasm::decode_at(self, instr_addr);
+            let bytes = (0..opcode.len as u16)
+                .map(|i| self.peek(instr_addr.wrapping_add(i)))
+                .collect();
+            trace(&TraceState {
+                pc: instr_addr,
+                register_a: self.register_a,
+                register_x: self.register_x,
+                register_y: self.register_y,
+                status: self.status,
+                register_s: self.register_s,
+                mnemonic,
+                bytes,
+            });
+            self.trace = Some(trace);
+        }
+
+        let mut halted = false;
+
+        match code {
+            0xA9 | 0xA5 | 0xB5 | 0xAD | 0xBD | 0xB9 | 0xA1 | 0xB1 => {
+                self.lda(&opcode.mode);
+            }
+            0x85 | 0x95 | 0x8D | 0x9D | 0x99 | 0x81 | 0x91 => {
+                self.sta(&opcode.mode);
             }
+            0xAA => self.tax(),
+            0xA8 => self.tay(),
+            0xE8 => self.inx(),
+            0xC8 => self.iny(),
+            0xCA => self.dex(),
+            0x88 => self.dey(),
+            0x20 => self.jsr(),
+            0x60 => self.rts(),
+            0x48 => self.pha(),
+            0x68 => self.pla(),
+            0x08 => self.php(),
+            0x28 => self.plp(),
+            0x40 => self.rti(),
 
-            if old_pc == self.pc {
-                self.pc += (opcode.len - 1) as u16
+            0x69 | 0x65 | 0x75 | 0x6D | 0x7D | 0x79 | 0x61 | 0x71 => {
+                self.adc(&opcode.mode);
             }
+            0xE9 | 0xE5 | 0xF5 | 0xED | 0xFD | 0xF9 | 0xE1 | 0xF1 => {
+                self.sbc(&opcode.mode);
+            }
+            0x29 | 0x25 | 0x35 | 0x2D | 0x3D | 0x39 | 0x21 | 0x31 => {
+                self.and(&opcode.mode);
+            }
+            0x49 | 0x45 | 0x55 | 0x4D | 0x5D | 0x59 | 0x41 | 0x51 => {
+                self.eor(&opcode.mode);
+            }
+            0x09 | 0x05 | 0x15 | 0x0D | 0x1D | 0x19 | 0x01 | 0x11 => {
+                self.ora(&opcode.mode);
+            }
+
+            0x0A => self.asl_accumulator(),
+            0x06 | 0x16 | 0x0E | 0x1E => self.asl(&opcode.mode),
+            0x4A => self.lsr_accumulator(),
+            0x46 | 0x56 | 0x4E | 0x5E => self.lsr(&opcode.mode),
+            0x2A => self.rol_accumulator(),
+            0x26 | 0x36 | 0x2E | 0x3E => self.rol(&opcode.mode),
+            0x6A => self.ror_accumulator(),
+            0x66 | 0x76 | 0x6E | 0x7E => self.ror(&opcode.mode),
+
+            0xC9 | 0xC5 | 0xD5 | 0xCD | 0xDD | 0xD9 | 0xC1 | 0xD1 => {
+                self.cmp(&opcode.mode);
+            }
+            0xE0 | 0xE4 | 0xEC => self.cpx(&opcode.mode),
+            0xC0 | 0xC4 | 0xCC => self.cpy(&opcode.mode),
+            0xE6 | 0xF6 | 0xEE | 0xFE => self.inc(&opcode.mode),
+            0xC6 | 0xD6 | 0xCE | 0xDE => self.dec(&opcode.mode),
+
+            0xA2 | 0xA6 | 0xB6 | 0xAE | 0xBE => self.ldx(&opcode.mode),
+            0xA0 | 0xA4 | 0xB4 | 0xAC | 0xBC => self.ldy(&opcode.mode),
+            0x86 | 0x96 | 0x8E => self.stx(&opcode.mode),
+            0x84 | 0x94 | 0x8C => self.sty(&opcode.mode),
+
+            0xBA => self.tsx(),
+            0x9A => self.txs(),
+            0x8A => self.txa(),
+            0x98 => self.tya(),
+
+            0x18 => self.set_carry_flag(false),
+            0x38 => self.set_carry_flag(true),
+            0x58 => self.status &= !FLAG_INTERRUPT_DISABLE,
+            0x78 => self.status |= FLAG_INTERRUPT_DISABLE,
+            0xB8 => self.set_overflow_flag(false),
+            0xD8 => self.status &= !FLAG_DECIMAL,
+            0xF8 => self.status |= FLAG_DECIMAL,
+
+            0x24 | 0x2C => self.bit(&opcode.mode),
+            0xEA => { /* NOP */ }
+
+            0x4C => self.jmp_absolute(),
+            0x6C => self.jmp_indirect(),
+
+            0x90 => self.branch(self.status & FLAG_CARRY == 0), // BCC
+            0xB0 => self.branch(self.status & FLAG_CARRY != 0), // BCS
+            0xF0 => self.branch(self.status & FLAG_ZERO != 0),  // BEQ
+            0xD0 => self.branch(self.status & FLAG_ZERO == 0),  // BNE
+            0x30 => self.branch(self.status & FLAG_NEGATIVE != 0), // BMI
+            0x10 => self.branch(self.status & FLAG_NEGATIVE == 0), // BPL
+            0x50 => self.branch(self.status & FLAG_OVERFLOW == 0), // BVC
+            0x70 => self.branch(self.status & FLAG_OVERFLOW != 0), // BVS
+
+            0x00 => {
+                self.brk();
+                halted = true;
+            }
+            _ => todo!(),
+        }
+
+        self.cycles += opcode.cycles as u64;
+        if self.page_crossed {
+            self.cycles += 1;
         }
+
+        if old_pc == self.pc {
+            self.pc += (opcode.len - 1) as u16
+        }
+
+        halted
     }
 }
 
@@ -272,6 +856,40 @@ mod test {
         assert_eq!(cpu.register_y, 1)
     }
 
+    #[test]
+    fn test_dex_overflow() {
+        let mut cpu = CPU::new();
+        cpu.load_and_run(vec![0xa9, 0x00, 0xaa, 0xca, 0x00]);
+        assert_eq!(cpu.register_x, 0xff)
+    }
+
+    #[test]
+    fn test_dey_overflow() {
+        let mut cpu = CPU::new();
+        cpu.load_and_run(vec![0xa9, 0x00, 0xa8, 0x88, 0x00]);
+        assert_eq!(cpu.register_y, 0xff)
+    }
+
+    #[test]
+    fn test_adc_carry_and_overflow() {
+        let mut cpu = CPU::new();
+        // LDA #$50; ADC #$50 -> 0xA0, signed overflow, no carry
+        cpu.load_and_run(vec![0xa9, 0x50, 0x69, 0x50, 0x00]);
+        assert_eq!(cpu.register_a, 0xa0);
+        assert!(cpu.status & FLAG_OVERFLOW != 0);
+        assert!(cpu.status & FLAG_CARRY == 0);
+    }
+
+    #[test]
+    fn test_sbc_carry_and_overflow() {
+        let mut cpu = CPU::new();
+        // LDA #$50; SEC; SBC #$B0 -> 0xA0, signed overflow, borrow (carry clear)
+        cpu.load_and_run(vec![0xa9, 0x50, 0x38, 0xe9, 0xb0, 0x00]);
+        assert_eq!(cpu.register_a, 0xa0);
+        assert!(cpu.status & FLAG_OVERFLOW != 0);
+        assert!(cpu.status & FLAG_CARRY == 0);
+    }
+
     #[test]
     fn test_lda_from_memory() {
         let mut cpu = CPU::new();
@@ -361,4 +979,73 @@ mod test {
 
         assert_eq!(cpu.mem_read_u16(0xa1), 0xaaaa)
     }
+
+    #[test]
+    fn test_jsr_rts_round_trip() {
+        // JSR $8004; BRK; LDA #$42; RTS
+        let mut cpu = CPU::new();
+        cpu.load_and_run(vec![0x20, 0x04, 0x80, 0x00, 0xa9, 0x42, 0x60]);
+        assert_eq!(cpu.register_a, 0x42);
+    }
+
+    #[test]
+    fn test_pha_pla_round_trip() {
+        let mut cpu = CPU::new();
+        cpu.load_and_run(vec![0xa9, 0x42, 0x48, 0xa9, 0x00, 0x68, 0x00]);
+        assert_eq!(cpu.register_a, 0x42);
+    }
+
+    #[test]
+    fn test_php_plp_round_trip() {
+        let mut cpu = CPU::new();
+        cpu.load_and_run(vec![0x38, 0x08, 0x18, 0x28, 0x00]);
+        assert!(cpu.status & FLAG_CARRY != 0);
+    }
+
+    #[test]
+    fn test_trace_callback_sees_instruction_bytes() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let seen: Rc<RefCell<Vec<Vec<u8>>>> = Rc::new(RefCell::new(Vec::new()));
+        let seen_clone = Rc::clone(&seen);
+
+        let mut cpu = CPU::new();
+        cpu.set_trace(Box::new(move |state: &TraceState| {
+            seen_clone.borrow_mut().push(state.bytes.clone());
+        }));
+
+        // JMP $C5F5
+        cpu.load_at(0xC000, &[0x4C, 0xF5, 0xC5]);
+        cpu.set_pc(0xC000);
+        cpu.run_until_trap(10);
+
+        assert_eq!(seen.borrow()[0], vec![0x4C, 0xF5, 0xC5]);
+    }
+
+    #[test]
+    fn test_absolute_x_page_cross_adds_a_cycle() {
+        let mut no_cross = CPU::new();
+        // LDX #$01; LDA $80C0,X -> $80C1, same page
+        no_cross.load_and_run(vec![0xa2, 0x01, 0xbd, 0xc0, 0x80, 0x00]);
+
+        let mut cross = CPU::new();
+        // LDX #$40; LDA $80C0,X -> $8100, crosses into the next page
+        cross.load_and_run(vec![0xa2, 0x40, 0xbd, 0xc0, 0x80, 0x00]);
+
+        assert_eq!(cross.cycles - no_cross.cycles, 1);
+    }
+
+    #[test]
+    fn test_run_until_trap_stops_at_self_jump() {
+        let mut cpu = CPU::new();
+        // JMP $C000, sitting at $C000, is the standard success/failure trap
+        // used by functional test ROMs.
+        cpu.load_at(0xC000, &[0x4C, 0x00, 0xC0]);
+        cpu.set_pc(0xC000);
+
+        let trap_pc = cpu.run_until_trap(1_000);
+
+        assert_eq!(trap_pc, 0xC000);
+    }
 }
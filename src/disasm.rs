@@ -0,0 +1,150 @@
+use crate::cpu::{AddressingMode, CPU};
+use crate::op_codes;
+
+/// Decodes the instruction at `addr`, reading bytes through `read`, and
+/// returns its formatted line plus the address of the next instruction.
+/// Taking a reader closure rather than a fixed slice lets callers decode
+/// straight out of a `CPU`'s bus or out of a raw program buffer.
+fn decode_one(read: impl Fn(u16) -> u8, addr: u16) -> (String, u16) {
+    let opcodes = &*op_codes::OP_CODES_MAP;
+    let code = read(addr);
+
+    let opcode = match opcodes.get(&code) {
+        Some(opcode) => opcode,
+        None => {
+            return (
+                format!("{:04X}  .byte ${:02X}", addr, code),
+                addr.wrapping_add(1),
+            )
+        }
+    };
+
+    let operand_len = (opcode.len as usize - 1).min(2);
+    let mut operand = [0u8; 2];
+    for (i, byte) in operand.iter_mut().enumerate().take(operand_len) {
+        *byte = read(addr.wrapping_add(1 + i as u16));
+    }
+
+    let operand_str = format_operand(code, addr, &opcode.mode, &operand, opcode.len);
+    let line = if operand_str.is_empty() {
+        format!("{:04X}  {}", addr, opcode.mnemonic)
+    } else {
+        format!("{:04X}  {} {}", addr, opcode.mnemonic, operand_str)
+    };
+
+    (line, addr.wrapping_add(opcode.len as u16))
+}
+
+fn format_operand(
+    code: u8,
+    addr: u16,
+    mode: &AddressingMode,
+    operand: &[u8; 2],
+    len: u8,
+) -> String {
+    match mode {
+        AddressingMode::Immediate => format!("#${:02X}", operand[0]),
+        AddressingMode::ZeroPage => format!("${:02X}", operand[0]),
+        AddressingMode::ZeroPage_X => format!("${:02X},X", operand[0]),
+        AddressingMode::ZeroPage_Y => format!("${:02X},Y", operand[0]),
+        AddressingMode::Absolute => {
+            let target = u16::from_le_bytes(*operand);
+            // JMP ($6C) is the only absolute-length instruction that reads
+            // through its operand rather than addressing it directly.
+            if code == 0x6C {
+                format!("(${:04X})", target)
+            } else {
+                format!("${:04X}", target)
+            }
+        }
+        AddressingMode::Absolute_X => format!("${:04X},X", u16::from_le_bytes(*operand)),
+        AddressingMode::Absolute_Y => format!("${:04X},Y", u16::from_le_bytes(*operand)),
+        AddressingMode::Indirect_X => format!("(${:02X},X)", operand[0]),
+        AddressingMode::Indirect_Y => format!("(${:02X}),Y", operand[0]),
+        AddressingMode::Relative => {
+            let offset = operand[0] as i8 as i16;
+            let target = (addr as i32 + 2 + offset as i32) as u16;
+            format!("${:04X}", target)
+        }
+        AddressingMode::NoneAddressing => {
+            if is_accumulator_shift(code) {
+                "A".to_string()
+            } else if code == 0x6C && len == 3 {
+                format!("(${:04X})", u16::from_le_bytes(*operand))
+            } else {
+                String::new()
+            }
+        }
+    }
+}
+
+fn is_accumulator_shift(code: u8) -> bool {
+    matches!(code, 0x0A | 0x4A | 0x2A | 0x6A)
+}
+
+/// Decodes the instruction starting at `addr`, reading through `cpu`'s bus.
+pub fn decode_at(cpu: &CPU, addr: u16) -> (String, u16) {
+    decode_one(|a| cpu.peek(a), addr)
+}
+
+/// Decodes the instruction currently sitting at `cpu.pc`.
+pub fn decode_at_pc(cpu: &CPU) -> (String, u16) {
+    decode_at(cpu, cpu.pc)
+}
+
+/// Disassembles `bytes` into one line per instruction, treating `bytes[0]`
+/// as living at `start_addr`.
+pub fn disassemble(bytes: &[u8], start_addr: u16) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut offset = 0usize;
+
+    while offset < bytes.len() {
+        let addr = start_addr.wrapping_add(offset as u16);
+        let (line, next_addr) = decode_one(
+            |a| {
+                let o = a.wrapping_sub(start_addr) as usize;
+                bytes.get(o).copied().unwrap_or(0)
+            },
+            addr,
+        );
+        offset += next_addr.wrapping_sub(addr) as usize;
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn disassemble_jmp_absolute() {
+        let lines = disassemble(&[0x4C, 0xF5, 0xC5], 0xC000);
+        assert_eq!(lines, vec!["C000  JMP $C5F5".to_string()]);
+    }
+
+    #[test]
+    fn disassemble_covers_every_addressing_mode() {
+        let cases: Vec<(&[u8], &str)> = vec![
+            (&[0xA9, 0x05], "C000  LDA #$05"),
+            (&[0xAD, 0x34, 0x12], "C000  LDA $1234"),
+            (&[0xA5, 0x10], "C000  LDA $10"),
+            (&[0xB5, 0x10], "C000  LDA $10,X"),
+            (&[0xB6, 0x10], "C000  LDX $10,Y"),
+            (&[0xBD, 0x34, 0x12], "C000  LDA $1234,X"),
+            (&[0xB9, 0x34, 0x12], "C000  LDA $1234,Y"),
+            (&[0xA1, 0x10], "C000  LDA ($10,X)"),
+            (&[0xB1, 0x10], "C000  LDA ($10),Y"),
+            (&[0xF0, 0x02], "C000  BEQ $C004"),
+            (&[0x0A], "C000  ASL A"),
+            (&[0x6C, 0x34, 0x12], "C000  JMP ($1234)"),
+            (&[0xFF], "C000  .byte $FF"),
+        ];
+
+        for (bytes, expected) in cases {
+            let lines = disassemble(bytes, 0xC000);
+            assert_eq!(lines, vec![expected.to_string()], "bytes: {:02X?}", bytes);
+        }
+    }
+}